@@ -10,7 +10,6 @@ use mynewt_macros::{
     init_strn,
 };
 
-//  TODO: Remove SPI settings for ST7789 display controller
 const DISPLAY_SPI: i32  =  0;  //  Mynewt SPI port 0
 const DISPLAY_CS: i32   = 25;  //  LCD_CS (P0.25): Chip select
 const DISPLAY_DC: i32   = 18;  //  LCD_RS (P0.18): Clock/data pin (CD)
@@ -18,124 +17,372 @@ const DISPLAY_RST: i32  = 26;  //  LCD_RESET (P0.26): Display reset
 const DISPLAY_HIGH: i32 = 23;  //  LCD_BACKLIGHT_{LOW,MID,HIGH} (P0.14, 22, 23): Backlight (active low)
 
 const SPI_NUM: i32 = DISPLAY_SPI;
-const SPI_SS_PIN: i32 = DISPLAY_CS;
-
-/// SPI settings for ST7789 display controller
-static mut SPI_SETTINGS: hal::hal_spi_settings = hal::hal_spi_settings {
-    data_order: hal::HAL_SPI_MSB_FIRST as u8,
-    data_mode:  hal::HAL_SPI_MODE3 as u8,  //  SPI must be used in mode 3. Mode 0 (the default) won't work.
-    baudrate:   8000,  //  In kHZ. Use SPI at 8MHz (the fastest clock available on the nRF52832) because otherwise refreshing will be super slow.
-    word_size:  hal::HAL_SPI_WORD_SIZE_8BIT as u8,
+
+/// SPI bus controller: owns the Mynewt SPI port together with the non-blocking transfer
+/// task, its completion semaphore, and the data/event queues for requests queued against
+/// this bus. Only `SPI0` is serviced: the task function, the mqueue callback, the completion
+/// handler and `SpiDevice::spi_noblock_write()` all work on `SPI0`, and every controller
+/// shares the one `spi_cb_obj` statistics block.
+pub struct SpiController {
+    num: i32,
+    sem: os::os_sem,
+    data_queue: os::os_mqueue,
+    event_queue: os::os_eventq,
+    callout: os::os_callout,
+    task: os::os_task,
+    task_stack: [os::os_stack_t; SPI_TASK_STACK_SIZE],
+    /// Current number of requests enqueued but not yet serviced by the task, used to
+    /// track `SpiStats::queue_high_water`.
+    queue_depth: u16,
+    /// Set once `init()` has created the task. Before that (e.g. early boot) there's
+    /// nobody to service `data_queue`, so `spi_noblock_write()` falls back to
+    /// `spi_write_polled_transfers()` instead of enqueuing a request that would never be serviced.
+    task_ready: bool,
+}
+
+/// SPI device attached to an `SpiController`'s bus: the transfer settings and chip-select
+/// pin to apply whenever a request for this device reaches the front of the queue.
+pub struct SpiDevice {
+    pub settings: hal::hal_spi_settings,
+    pub cs_pin: i32,
+}
+
+/// The single non-blocking SPI bus shared by every device below (Mynewt SPI port 0)
+pub static mut SPI0: SpiController = SpiController {
+    num: SPI_NUM,
+    sem: fill_zero!(os::os_sem),
+    data_queue: fill_zero!(os::os_mqueue),
+    event_queue: fill_zero!(os::os_eventq),
+    callout: fill_zero!(os::os_callout),
+    task: fill_zero!(os::os_task),
+    task_stack: [0; SPI_TASK_STACK_SIZE],
+    queue_depth: 0,
+    task_ready: false,
+};
+
+/// ST7789 display controller, wired to `SPI0`
+pub static DISPLAY: SpiDevice = SpiDevice {
+    settings: hal::hal_spi_settings {
+        data_order: hal::HAL_SPI_MSB_FIRST as u8,
+        data_mode:  hal::HAL_SPI_MODE3 as u8,  //  SPI must be used in mode 3. Mode 0 (the default) won't work.
+        baudrate:   8000,  //  In kHZ. Use SPI at 8MHz (the fastest clock available on the nRF52832) because otherwise refreshing will be super slow.
+        word_size:  hal::HAL_SPI_WORD_SIZE_8BIT as u8,
+    },
+    cs_pin: DISPLAY_CS,
 };
 
-/// Non-blocking SPI transfer callback parameter
-struct spi_cb_arg {
-    transfers: i32,
-    txlen: i32,
-    tx_rx_bytes: u32,
+/// Max number of chained `SpiTransfer` segments carried by a single queued request.
+const SPI_MAX_TRANSFERS: usize = 4;
+
+/// One segment of a chained SPI transfer: a command byte sent with CS held, followed by a
+/// data payload, or a full-duplex read where `rx` captures the bytes clocked back in.
+pub struct SpiTransfer<'a> {
+    pub tx: &'a [u8],
+    /// Written asynchronously by the SPI task, after `spi_noblock_write()` has already
+    /// returned -- unlike `tx`, which is copied eagerly, this buffer must outlive the
+    /// whole request or the write lands in freed memory.
+    pub rx: Option<&'a mut [u8]>,
+    /// Deassert CS after this segment completes. Set `false` to keep CS held into the
+    /// next segment of the same request (e.g. a command byte immediately followed by data).
+    pub cs_change: bool,
+    /// Busy-wait this many microseconds after the segment completes (and after CS is
+    /// deasserted, if `cs_change` is set), before starting the next one. On a last segment
+    /// with `cs_change` unset, CS is released after the wait.
+    pub delay_usecs: u16,
+}
+
+/// Per-segment metadata for one transfer in a chained request, stashed (alongside the
+/// device it's for) in the mbuf's user header. `len` is this segment's TX byte count,
+/// consumed in order from the request's mbuf chain; `rx` points at the caller's receive
+/// buffer, which the caller must keep alive until the transfer completes.
+#[derive(Clone, Copy)]
+struct SpiTransferMeta {
+    len: u16,
+    cs_change: bool,
+    delay_usecs: u16,
+    rx: Ptr,
+}
+
+/// Queued request header, stashed in the lead mbuf's user header: which device the
+/// request is for, and the per-segment metadata for each chained transfer.
+struct SpiRequestHdr {
+    device: &'static SpiDevice,
+    num_transfers: u8,
+    transfers: [SpiTransferMeta; SPI_MAX_TRANSFERS],
+}
+
+/// Non-blocking SPI transfer statistics: total segments completed, total bytes
+/// transferred, the deepest the request queue has gotten, and how many enqueue attempts
+/// failed for lack of memory. Read with `spi_stats()`.
+#[derive(Clone, Copy)]
+pub struct SpiStats {
+    pub transfers: i32,
+    pub txlen: i32,
+    pub tx_rx_bytes: u32,
+    pub queue_high_water: u16,
+    pub enqueue_failures: u32,
 }
 
-/// Non-blocking SPI transfer callback values
-static mut spi_cb_obj: spi_cb_arg = spi_cb_arg {
+/// Non-blocking SPI transfer statistics, also passed to `hal_spi_set_txrx_cb()` as the
+/// callback argument so `spi_noblock_handler()` can update it directly on every completion.
+static mut spi_cb_obj: SpiStats = SpiStats {
     transfers: 0,
     txlen: 0,
     tx_rx_bytes: 0,
+    queue_high_water: 0,
+    enqueue_failures: 0,
 };
 
-/// Semaphore that is signalled for every completed SPI request
-static mut SPI_SEM: os::os_sem = fill_zero!(os::os_sem);
-static mut SPI_DATA_QUEUE: os::os_mqueue = fill_zero!(os::os_mqueue);
-static mut SPI_EVENT_QUEUE: os::os_eventq = fill_zero!(os::os_eventq);
-
-/// Callout that is invoked when non-blocking SPI transfer is completed
-static mut spi_callout: os::os_callout = fill_zero!(os::os_callout);
+/// Read the current non-blocking SPI transfer statistics.
+pub fn spi_stats() -> SpiStats {
+    unsafe { spi_cb_obj }
+}
 
-///  Storage for SPI Task: Mynewt task object will be saved here.
-static mut SPI_TASK: os::os_task = fill_zero!(os::os_task);
-///  Stack space for SPI Task, initialised to 0.
-static mut SPI_TASK_STACK: [os::os_stack_t; SPI_TASK_STACK_SIZE] = 
-    [0; SPI_TASK_STACK_SIZE];
-///  Size of the stack (in 4-byte units). Previously `OS_STACK_ALIGN(256)`  
+///  Size of the SPI task's stack (in 4-byte units). Previously `OS_STACK_ALIGN(256)`
 const SPI_TASK_STACK_SIZE: usize = 256;
 
-/// Init non-blocking SPI transfer
-pub fn spi_noblock_init() -> MynewtResult<()> {
-    unsafe { hal::hal_spi_disable(SPI_NUM) };
+impl SpiController {
+    /// Init non-blocking SPI transfer for this bus. Must be called once before any
+    /// `SpiDevice` wired to it can enqueue a transfer.
+    pub fn init(&mut self) -> MynewtResult<()> {
+        let arg = unsafe { core::mem::transmute(&mut spi_cb_obj) };
+        let rc = unsafe { hal::hal_spi_set_txrx_cb(self.num, Some(spi_noblock_handler), arg) };
+        assert_eq!(rc, 0, "spi cb fail");  //  TODO: Map to MynewtResult
+
+        let rc = unsafe { hal::hal_spi_enable(self.num) };
+        assert_eq!(rc, 0, "spi enable fail");  //  TODO: Map to MynewtResult
+
+        unsafe { os::os_eventq_init(&mut self.event_queue) };
+
+        let rc = unsafe { os::os_mqueue_init(&mut self.data_queue, Some(spi_event_callback), NULL) };
+        assert_eq!(rc, 0, "mqueue fail");  //  TODO: Map to MynewtResult
+
+        let rc = unsafe { os::os_sem_init(&mut self.sem, 0) };  //  Init to 0 tokens, so caller will block until SPI request is completed.
+        assert_eq!(rc, 0, "sem fail");  //  TODO: Map to MynewtResult
+
+        os::task_init(               //  Create a new task and start it...
+            &mut self.task,           //  Task object will be saved here
+            &init_strn!( "spi" ),     //  Name of task
+            Some( spi_task_func ),    //  Function to execute when task starts
+            NULL,  //  Argument to be passed to above function
+            10,    //  Task priority: highest is 0, lowest is 255 (main task is 127)
+            os::OS_WAIT_FOREVER as u32,  //  Don't do sanity / watchdog checking
+            &mut self.task_stack,        //  Stack space for the task
+            SPI_TASK_STACK_SIZE as u16   //  Size of the stack (in 4-byte units)
+        ) ? ;                            //  `?` means check for error
+
+        //  Init the callout to handle completed SPI transfers.
+        unsafe {
+            os::os_callout_init(
+                &mut self.callout,
+                os::eventq_dflt_get() ? ,
+                Some(spi_noblock_callback),
+                core::ptr::null_mut()
+            )
+        };
+        self.task_ready = true;
+        Ok(())
+    }
 
-    let rc = unsafe { hal::hal_spi_config(SPI_NUM, &mut SPI_SETTINGS) };
-    assert_eq!(rc, 0, "spi config fail");  //  TODO: Map to MynewtResult
+    /// Reconfigure the bus with `device`'s settings, ready to transmit a queued request for it.
+    fn select(&self, device: &'static SpiDevice) {
+        unsafe { hal::hal_spi_disable(self.num) };
+        let mut settings = device.settings;
+        let rc = unsafe { hal::hal_spi_config(self.num, &mut settings) };
+        assert_eq!(rc, 0, "spi config fail");  //  TODO: Map to MynewtResult
+        unsafe { hal::hal_spi_enable(self.num) };
+    }
+}
 
-    let arg = unsafe { core::mem::transmute(&mut spi_cb_obj) };
-    let rc = unsafe { hal::hal_spi_set_txrx_cb(SPI_NUM, Some(spi_noblock_handler), arg) };
-    assert_eq!(rc, 0, "spi cb fail");  //  TODO: Map to MynewtResult
+impl SpiDevice {
+    /// Register this device's chip-select pin as a GPIO output, deasserted (high). Must be
+    /// called once before this device's first transfer, queued or polled; it does not
+    /// depend on `SPI0.init()`.
+    pub fn init(&self) -> MynewtResult<()> {
+        let rc = unsafe { hal::hal_gpio_init_out(self.cs_pin, 1) };
+        assert_eq!(rc, 0, "gpio fail");  //  TODO: Map to MynewtResult
+        Ok(())
+    }
 
-    let rc = unsafe { hal::hal_spi_enable(SPI_NUM) };
-    assert_eq!(rc, 0, "spi enable fail");  //  TODO: Map to MynewtResult
+    /// Synchronous, polled SPI write for a single buffer. Usable from contexts where
+    /// blocking on a semaphore is illegal — `os_arch_in_isr()` true, or early boot before
+    /// `SPI0.init()` has created the SPI task.
+    pub fn spi_write_polled(&self, words: &[u8]) -> MynewtResult<()> {
+        let mut transfers = [ SpiTransfer { tx: words, rx: None, cs_change: true, delay_usecs: 0 } ];
+        self.spi_write_polled_transfers(&mut transfers)
+    }
 
-    let rc = unsafe { hal::hal_gpio_init_out(SPI_SS_PIN, 1) };
-    assert_eq!(rc, 0, "gpio fail");  //  TODO: Map to MynewtResult
+    /// Synchronous, polled version of `spi_noblock_write()`: replays each chained segment
+    /// in turn, honoring `cs_change`/`rx`/`delay_usecs` just like `spi_event_callback`'s
+    /// dequeue loop, but blocking in place instead of queuing the request for the SPI task.
+    pub fn spi_write_polled_transfers(&self, transfers: &mut [SpiTransfer]) -> MynewtResult<()> {
+        unsafe { hal::hal_spi_disable(SPI_NUM) };
+        let mut settings = self.settings;
+        let rc = unsafe { hal::hal_spi_config(SPI_NUM, &mut settings) };
+        assert_eq!(rc, 0, "spi config fail");  //  TODO: Map to MynewtResult
+        unsafe { hal::hal_spi_enable(SPI_NUM) };
+
+        let mut cs_asserted = false;
+        for t in transfers.iter_mut() {
+            if !cs_asserted {
+                //  Set the CS Pin to low to start the transfer.
+                unsafe { hal::hal_gpio_write(self.cs_pin, 0) };
+                cs_asserted = true;
+            }
+
+            let rxbuffer = match &mut t.rx {
+                Some(rx) => {
+                    assert_eq!(rx.len(), t.tx.len(), "rx/tx length mismatch");
+                    rx.as_mut_ptr() as Ptr
+                }
+                None => NULL,
+            };
+            let rc = unsafe { hal::hal_spi_txrx(SPI_NUM, t.tx.as_ptr() as Ptr, rxbuffer, t.tx.len() as i32) };
+            assert_eq!(rc, 0, "spi fail");  //  TODO: Map to MynewtResult
+
+            if t.cs_change {
+                //  Set the CS Pin to high between segments, unless the caller wants it
+                //  held low into the next one (e.g. a command byte followed by data).
+                unsafe { hal::hal_gpio_write(self.cs_pin, 1) };
+                cs_asserted = false;
+            }
+            if t.delay_usecs > 0 {
+                unsafe { os::os_cputime_delay_usecs(t.delay_usecs as u32) };
+            }
+        }
+        if cs_asserted { unsafe { hal::hal_gpio_write(self.cs_pin, 1) }; }
+        Ok(())
+    }
 
-    unsafe { os::os_eventq_init(&mut SPI_EVENT_QUEUE) };
+    /// Enqueue request for non-blocking SPI write to this device. Returns without waiting
+    /// for write to complete. Makes a copy of every transfer's `tx` bytes in an mbuf, so the
+    /// caller's `tx` buffers may be reused immediately; `rx` buffers must stay alive until
+    /// the request completes.
+    ///
+    /// Auto-selects `spi_write_polled_transfers()` instead when there's no SPI task yet to
+    /// service the request, or when called from an interrupt where blocking is illegal.
+    #[cfg(feature = "spi_noblock")]
+    pub fn spi_noblock_write(&'static self, transfers: &mut [SpiTransfer]) -> MynewtResult<()> {
+        if unsafe { !SPI0.task_ready } || os::os_arch_in_isr() {
+            return self.spi_write_polled_transfers(transfers);
+        }
+        assert!(transfers.len() <= SPI_MAX_TRANSFERS, "too many transfers");
+        let total_len: usize = transfers.iter().map(|t| t.tx.len()).sum();
+
+        //  Allocate a packet header mbuf, reserving room in the user header for the
+        //  device and per-segment metadata the SPI task needs to replay this request.
+        let usrhdr_len = core::mem::size_of::<SpiRequestHdr>() as u16;
+        let om = unsafe { os::os_msys_get_pkthdr(total_len as u16, usrhdr_len) };
+        if om.is_null() {
+            unsafe { spi_cb_obj.enqueue_failures += 1 };
+            return Err(MynewtError::SYS_ENOMEM);  //  If out of memory, quit.
+        }
 
-    let rc = unsafe { os::os_mqueue_init(&mut SPI_DATA_QUEUE, Some(spi_event_callback), NULL) };
-    assert_eq!(rc, 0, "mqueue fail");  //  TODO: Map to MynewtResult
+        //  Build the per-segment metadata and append each segment's TX bytes, back to back,
+        //  to the mbuf chain.  This may increase the number of mbufs in the chain.
+        let mut hdr = SpiRequestHdr {
+            device: self,
+            num_transfers: transfers.len() as u8,
+            transfers: [SpiTransferMeta { len: 0, cs_change: true, delay_usecs: 0, rx: NULL }; SPI_MAX_TRANSFERS],
+        };
+        for (i, t) in transfers.iter_mut().enumerate() {
+            if let Some(rx) = &t.rx {
+                assert_eq!(rx.len(), t.tx.len(), "rx/tx length mismatch");
+            }
+            hdr.transfers[i] = SpiTransferMeta {
+                len: t.tx.len() as u16,
+                cs_change: t.cs_change,
+                delay_usecs: t.delay_usecs,
+                rx: match &mut t.rx { Some(rx) => rx.as_mut_ptr() as Ptr, None => NULL },
+            };
+            let rc = unsafe { os::os_mbuf_append(om, t.tx.as_ptr() as Ptr, t.tx.len() as u16) };
+            if rc != 0 {
+                unsafe { os::os_mbuf_free_chain(om) };
+                unsafe { spi_cb_obj.enqueue_failures += 1 };
+                return Err(MynewtError::SYS_ENOMEM);  //  If out of memory, quit.
+            }
+        }
 
-    let rc = unsafe { os::os_sem_init(&mut SPI_SEM, 0) };  //  Init to 0 tokens, so caller will block until SPI request is completed.
-    assert_eq!(rc, 0, "sem fail");  //  TODO: Map to MynewtResult
+        //  Stash the request header in the mbuf's user header.
+        let usrhdr = unsafe { os::OS_MBUF_USRHDR(om, *mut SpiRequestHdr) };
+        unsafe { core::ptr::write(usrhdr, hdr) };
+
+        //  Count the request as queued before handing it over: the SPI task runs at a higher
+        //  priority and may dequeue it before `os_mqueue_put()` returns. Track the deepest
+        //  the queue has gotten, for `spi_stats()`.
+        unsafe {
+            SPI0.queue_depth += 1;
+            if SPI0.queue_depth > spi_cb_obj.queue_high_water {
+                spi_cb_obj.queue_high_water = SPI0.queue_depth;
+            }
+        }
 
-    os::task_init(                //  Create a new task and start it...
-        unsafe { &mut SPI_TASK }, //  Task object will be saved here
-        &init_strn!( "spi" ),     //  Name of task
-        Some( spi_task_func ),    //  Function to execute when task starts
-        NULL,  //  Argument to be passed to above function
-        10,    //  Task priority: highest is 0, lowest is 255 (main task is 127)
-        os::OS_WAIT_FOREVER as u32,     //  Don't do sanity / watchdog checking
-        unsafe { &mut SPI_TASK_STACK }, //  Stack space for the task
-        SPI_TASK_STACK_SIZE as u16      //  Size of the stack (in 4-byte units)
-    ) ? ;                               //  `?` means check for error
-
-    //  Init the callout to handle completed SPI transfers.
-    unsafe {
-        os::os_callout_init(
-            &mut spi_callout, 
-            os::eventq_dflt_get() ? , 
-            Some(spi_noblock_callback), 
-            core::ptr::null_mut()
-        )
-    };
-    Ok(())
+        //  Enqueue the mbuf chain and wake up the SPI task to transmit it.
+        let rc = unsafe { os::os_mqueue_put(&mut SPI0.data_queue, &mut SPI0.event_queue, om) };
+        if rc != 0 {
+            unsafe { SPI0.queue_depth -= 1 };
+            unsafe { os::os_mbuf_free_chain(om) };
+            unsafe { spi_cb_obj.enqueue_failures += 1 };
+            return Err(MynewtError::SYS_ENOMEM);  //  If out of memory, quit.
+        }
+        Ok(())
+    }
 }
 
-/// Enqueue request for non-blocking SPI write. Returns without waiting for write to complete.
-#[cfg(feature = "spi_noblock")]
-pub fn spi_noblock_write(words: &[u8]) -> MynewtResult<()> {
-    //  Add to request queue. Make a copy of the data to be sent.
-
-    //  struct os_mbuf *semihost_mbuf = os_msys_get_pkthdr(length, 0);
-    //  if (!semihost_mbuf) { return; }  //  If out of memory, quit.
-
-    //  Append the data to the mbuf chain.  This may increase the numbere of mbufs in the chain.
-    //  rc = os_mbuf_append(semihost_mbuf, buffer, length);
-    //  if (rc) { return; }  //  If out of memory, quit.
-
-    //  rc = os_mqueue_put(&SPI_DATA_QUEUE, &SPI_EVENT_QUEUE, om);
-    //  if (rc) { return; }  //  If out of memory, quit.
-
-    Ok(())
+/// DMA-backed transfer path for large display refreshes, used instead of the byte-level
+/// `hal_spi_txrx_noblock` callback when the `spi_dma` feature is enabled. Programs the
+/// nRF52832 SPIM0 peripheral's EasyDMA `TXD.PTR`/`TXD.MAXCNT` registers directly; completion
+/// is busy-polled and then reported by calling the same `spi_noblock_handler()` that is
+/// registered with `hal_spi_set_txrx_cb`, rather than through a second ISR.
+#[cfg(feature = "spi_dma")]
+mod dma {
+    use crate::Ptr;
+
+    /// Below this length, EasyDMA setup overhead isn't worth it; `internal_spi_noblock_write`
+    /// falls back to the byte-level `hal_spi_txrx_noblock` path instead.
+    pub const THRESHOLD: usize = 64;
+
+    const SPIM0_BASE:  u32 = 0x4000_3000;  //  nRF52832 SPIM0 peripheral base address
+    const TASKS_START: u32 = SPIM0_BASE + 0x010;
+    const EVENTS_END:  u32 = SPIM0_BASE + 0x118;
+    const TXD_PTR:     u32 = SPIM0_BASE + 0x544;
+    const TXD_MAXCNT:  u32 = SPIM0_BASE + 0x548;
+
+    /// Program EasyDMA for `txbuffer`/`txlen`, kick off the transfer, and busy-poll
+    /// `EVENTS_END` until it completes. Completion is reported by the caller, through
+    /// `spi_noblock_handler()`, exactly as the non-DMA path does.
+    pub unsafe fn start(txbuffer: Ptr, txlen: u16) {
+        core::ptr::write_volatile(TXD_PTR as *mut u32, txbuffer as u32);
+        core::ptr::write_volatile(TXD_MAXCNT as *mut u32, txlen as u32);
+        core::ptr::write_volatile(EVENTS_END as *mut u32, 0);   //  Clear any stale event.
+        core::ptr::write_volatile(TASKS_START as *mut u32, 1);  //  Kick off the DMA transfer.
+        while core::ptr::read_volatile(EVENTS_END as *const u32) == 0 {}
+    }
 }
 
-/// Perform non-blocking SPI write.  Returns without waiting for write to complete.
+/// Perform one SPI transfer segment to the currently selected device. Large TX-only
+/// transfers go over EasyDMA (feature `spi_dma`): that path busy-waits until the transfer
+/// has ended and reports completion before returning. Everything else, or if DMA is
+/// unavailable, uses the byte-level `hal_spi_txrx_noblock` callback path, which returns
+/// without waiting for the transfer to complete.
 #[cfg(feature = "spi_noblock")]
-fn internal_spi_noblock_write(txbuffer: Ptr, txlen: i32) -> MynewtResult<()> {
+fn internal_spi_noblock_write(txbuffer: Ptr, rxbuffer: Ptr, txlen: i32) -> MynewtResult<()> {
     unsafe { spi_cb_obj.txlen = txlen };
-    //  Set the SS Pin to low to start the transfer.
-    unsafe { hal::hal_gpio_write(SPI_SS_PIN, 0) };
 
-    //  Write the SPI data.
+    #[cfg(feature = "spi_dma")]
+    {
+        if rxbuffer.is_null() && txlen as usize >= dma::THRESHOLD {
+            unsafe { dma::start(txbuffer, txlen as u16) };
+            let arg = unsafe { core::mem::transmute(&mut spi_cb_obj) };
+            spi_noblock_handler(arg, txlen);
+            return Ok(());
+        }
+    }
+
     let rc = unsafe { hal::hal_spi_txrx_noblock(
-        SPI_NUM, 
+        SPI_NUM,
         txbuffer, //  TX Buffer
-        NULL,     //  RX Buffer (don't receive)        
+        rxbuffer, //  RX Buffer (NULL if caller doesn't want received bytes)
         txlen) };
     assert_eq!(rc, 0, "spi fail");  //  TODO: Map to MynewtResult
     Ok(())
@@ -145,15 +392,65 @@ fn internal_spi_noblock_write(txbuffer: Ptr, txlen: i32) -> MynewtResult<()> {
 extern "C" fn spi_event_callback(_event: *mut os::os_event) {
     loop {
         //  Get the next data packet.
-        let om = unsafe { os::os_mqueue_get(&mut SPI_DATA_QUEUE) };
+        let om = unsafe { os::os_mqueue_get(&mut SPI0.data_queue) };
         if om.is_null() { break; }
-
-        //  TODO: Write the data packet
-
-        //  Wait for spi_noblock_handler() to signal that SPI request has been completed.
-        let timeout = 1000;
-        let OS_TICKS_PER_SEC = 1000;
-        unsafe { os::os_sem_pend(&mut SPI_SEM, timeout * OS_TICKS_PER_SEC / 1000) };
+        unsafe { SPI0.queue_depth -= 1 };
+
+        //  Look up which device this request is for and reconfigure the bus with its
+        //  settings before transmitting, so a single task can serialize transfers to
+        //  every device wired to the bus.
+        let hdr = unsafe { &*os::OS_MBUF_USRHDR(om, *const SpiRequestHdr) };
+        unsafe { SPI0.select(hdr.device) };
+
+        //  `os_mbuf_append()` may have split the concatenated TX bytes across more than
+        //  one physical mbuf in the chain, so a transfer's bytes can't be assumed to live
+        //  under one contiguous pointer. Walk the chain with a cursor that persists across
+        //  transfers, consuming each transfer's bytes one physical segment at a time.
+        let mut seg = om;
+        let mut seg_pos: u16 = 0;
+        let mut cs_asserted = false;
+        for i in 0 .. hdr.num_transfers as usize {
+            let t = &hdr.transfers[i];
+            if !cs_asserted {
+                //  Set the CS Pin to low to start the transfer.
+                unsafe { hal::hal_gpio_write(hdr.device.cs_pin, 0) };
+                cs_asserted = true;
+            }
+
+            let mut remaining = t.len;
+            let mut rx = t.rx;
+            while remaining > 0 {
+                while seg_pos == unsafe { (*seg).om_len } {
+                    seg = unsafe { (*seg).om_next.sle_next };
+                    seg_pos = 0;
+                }
+                let data = unsafe { os::OS_MBUF_DATA(seg, Ptr) } as *mut u8;
+                let chunk = core::cmp::min(unsafe { (*seg).om_len } - seg_pos, remaining);
+                let txbuffer = unsafe { data.add(seg_pos as usize) } as Ptr;
+                internal_spi_noblock_write(txbuffer, rx, chunk as i32)
+                    .expect("spi write fail");  //  TODO: Map to MynewtResult
+
+                //  Wait for spi_noblock_handler() to signal that this segment has completed.
+                let timeout = 1000;
+                let OS_TICKS_PER_SEC = 1000;
+                unsafe { os::os_sem_pend(&mut SPI0.sem, timeout * OS_TICKS_PER_SEC / 1000) };
+
+                seg_pos += chunk;
+                remaining -= chunk;
+                if !rx.is_null() { rx = unsafe { (rx as *mut u8).add(chunk as usize) } as Ptr; }
+            }
+
+            if t.cs_change {
+                //  Set the CS Pin to high between segments, unless the caller wants it
+                //  held low into the next one (e.g. a command byte followed by data).
+                unsafe { hal::hal_gpio_write(hdr.device.cs_pin, 1) };
+                cs_asserted = false;
+            }
+            if t.delay_usecs > 0 {
+                unsafe { os::os_cputime_delay_usecs(t.delay_usecs as u32) };
+            }
+        }
+        if cs_asserted { unsafe { hal::hal_gpio_write(hdr.device.cs_pin, 1) }; }
 
         //  Free the data packet.
         unsafe { os::os_mbuf_free_chain(om) };
@@ -164,22 +461,27 @@ extern "C" fn spi_event_callback(_event: *mut os::os_event) {
 extern "C" fn spi_task_func(_arg: Ptr) {
     loop {
         os::eventq_run(
-            unsafe { &mut SPI_EVENT_QUEUE }
+            unsafe { &mut SPI0.event_queue }
         ).expect("eventq fail");
     }
 }
 
-/// Called by interrupt handler after Non-blocking SPI transfer has completed
-extern "C" fn spi_noblock_handler(_arg: *mut core::ffi::c_void, _len: i32) {
-    //  Set SS Pin to high to stop the transfer.
-    unsafe { hal::hal_gpio_write(SPI_SS_PIN, 1) };
+/// Called by interrupt handler after a non-blocking SPI transfer segment has completed.
+/// CS is driven by `spi_event_callback`'s dequeue loop (per segment's `cs_change`), not here.
+extern "C" fn spi_noblock_handler(arg: *mut core::ffi::c_void, len: i32) {
+    if !arg.is_null() {
+        let cb = unsafe { &mut *(arg as *mut SpiStats) };
+        assert_eq!(len, cb.txlen, "spi len mismatch");
+        cb.transfers += 1;
+        cb.tx_rx_bytes += len as u32;
+    }
 
     //  Trigger the callout to transmit next SPI request.
-    unsafe { os::os_callout_reset(&mut spi_callout, 0) };
+    unsafe { os::os_callout_reset(&mut SPI0.callout, 0) };
 
-    //  Signal to internal_spi_noblock_write() that SPI request has been completed.
-    //  os_error_t rc = os_sem_release(&SPI_SEM);
-    //  assert(rc == OS_OK);
+    //  Signal to spi_event_callback() that this segment has been completed.
+    let rc = unsafe { os::os_sem_release(&mut SPI0.sem) };
+    assert_eq!(rc, 0, "sem fail");  //  TODO: Map to MynewtResult
 }
 
 /// Callout after Non-blocking SPI transfer as completed